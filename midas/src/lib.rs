@@ -18,6 +18,10 @@
 //! ```
 
 use rand::rngs::SmallRng;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 
 pub mod default {
     use super::{Float, Int};
@@ -26,12 +30,73 @@ pub mod default {
     pub const NUM_BUCKETS: Int = 769;
     pub const M_VALUE: Int = 773;
     pub const ALPHA: Float = 0.6;
+    pub const THRESHOLD: Float = 3.0;
 }
 
 pub type Int = u64;
 pub type Float = f64;
 const FLOAT_MAX: Float = std::f64::MAX;
 
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// A small, non-cryptographic hasher (the multiply-xor-rotate finalizer
+/// popularized by rustc's FxHash) used to reduce arbitrary node
+/// identifiers down to the `Int` the Count-Min Sketch math runs on.
+#[derive(Default)]
+struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    fn write_word(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FX_SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        while bytes.len() >= 8 {
+            let (chunk, rest) = bytes.split_at(8);
+            self.write_word(u64::from_ne_bytes(chunk.try_into().unwrap()));
+            bytes = rest;
+        }
+
+        if !bytes.is_empty() {
+            let mut buf = [0u8; 8];
+            buf[..bytes.len()].copy_from_slice(bytes);
+            self.write_word(u64::from_ne_bytes(buf));
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Reduces any `Hash` identifier down to the `Int` used internally by
+/// `Row::hash`, so callers aren't required to pre-map node identifiers
+/// to integers themselves.
+fn hash_id<T: Hash + ?Sized>(id: &T) -> Int {
+    let mut hasher = FxHasher::default();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Like [`hash_id`], but leaves an already-`Int` id untouched instead of
+/// hashing it. Stable Rust has no specialization, so this is done with a
+/// runtime `Any` downcast rather than a trait overlap; it's only ever hit
+/// once per id per insert/query, not in the hot CMS loop. This keeps `MidasR`
+/// (`MidasRGeneric<Int, Int>`) producing the exact same scores it did
+/// before `MidasRGeneric` existed, while non-`Int` id types still get
+/// reduced via `hash_id`.
+fn reduce_id<T: Hash + 'static>(id: &T) -> Int {
+    match (id as &dyn std::any::Any).downcast_ref::<Int>() {
+        Some(&int_id) => int_id,
+        None => hash_id(id),
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Row {
     a: Int,
     b: Int,
@@ -97,6 +162,23 @@ impl Row {
             *bucket = *bucket * alpha;
         }
     }
+
+    /// Folds `current`'s buckets into `self`'s buckets one at a time,
+    /// scaling down the contribution of any bucket whose anomaly score
+    /// (computed from the pre-merge totals) is at or above `threshold`.
+    /// This keeps an ongoing burst from being fully absorbed into the
+    /// baseline it is supposed to be measured against.
+    fn merge_from(&mut self, current: &Row, current_time: Int, threshold: Float) {
+        for (total, current) in self.buckets.iter_mut().zip(current.buckets.iter()) {
+            let score = counts_to_anom(*total, *current, current_time);
+
+            if score < threshold {
+                *total += current;
+            } else {
+                *total += current * (threshold / score);
+            }
+        }
+    }
 }
 
 struct Rng(SmallRng);
@@ -113,6 +195,7 @@ impl Rng {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct EdgeHash {
     m_value: Int,
     rows: Vec<Row>,
@@ -152,8 +235,18 @@ impl EdgeHash {
             .map(|row| row.count(self.m_value, source, dest))
             .fold(FLOAT_MAX, float_min)
     }
+
+    /// See [`Row::merge_from`]. `self` and `current` must have been built
+    /// with the same `rows`/`buckets`/`m_value` and seed, so their rows and
+    /// per-row hash coefficients line up positionally.
+    fn merge_from(&mut self, current: &EdgeHash, current_time: Int, threshold: Float) {
+        for (total_row, current_row) in self.rows.iter_mut().zip(current.rows.iter()) {
+            total_row.merge_from(current_row, current_time, threshold);
+        }
+    }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct NodeHash {
     rows: Vec<Row>,
 }
@@ -204,6 +297,14 @@ fn float_min(a: Float, b: Float) -> Float {
 }
 
 fn counts_to_anom(total: Float, current: Float, current_time: Int) -> Float {
+    // With no prior baseline there's nothing to compare `current`
+    // against yet; treat it as not anomalous rather than dividing by a
+    // zero mean (which, for `current_time == 0`, would also underflow
+    // the unsigned `current_time - 1` below).
+    if total == 0. {
+        return 0.;
+    }
+
     let current_mean = total / current_time as Float;
     let sqerr = float_max(0., current - current_mean).powi(2);
     (sqerr / current_mean) + (sqerr / (current_mean * float_max(1., (current_time - 1) as Float)))
@@ -233,7 +334,18 @@ impl Default for MidasRParams {
     }
 }
 
-pub struct MidasR {
+/// The core MIDAS-R implementation, generic over the source/destination
+/// identifier types. `source`/`dest` are reduced to the internal `Int`
+/// used by the Count-Min Sketch math via [`reduce_id`], so `S`/`D` only
+/// need to be `Hash + 'static` - they never need to already be integers. An `S`/`D`
+/// that already *is* `Int` (as with the [`MidasR`] alias) is passed
+/// through unchanged rather than re-hashed.
+///
+/// Most callers want the [`MidasR`] alias rather than naming this type
+/// directly.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct MidasRGeneric<S, D> {
     current_time: Int,
     alpha: Float,
 
@@ -244,9 +356,16 @@ pub struct MidasR {
     dest_score: NodeHash,
     source_total: NodeHash,
     dest_total: NodeHash,
+
+    _marker: PhantomData<(S, D)>,
 }
 
-impl MidasR {
+/// `Int`/`Int` specialization kept for backward compatibility: since
+/// `reduce_id` passes an already-`Int` id through unchanged, this behaves
+/// exactly like the original, non-generic `MidasR`.
+pub type MidasR = MidasRGeneric<Int, Int>;
+
+impl<S: Hash + 'static, D: Hash + 'static> MidasRGeneric<S, D> {
     pub fn new(
         MidasRParams {
             rows,
@@ -268,6 +387,8 @@ impl MidasR {
             dest_score: NodeHash::new(rows, buckets, dumb_seed + 4),
             source_total: NodeHash::new(rows, buckets, dumb_seed + 5),
             dest_total: NodeHash::new(rows, buckets, dumb_seed + 6),
+
+            _marker: PhantomData,
         }
     }
 
@@ -284,13 +405,27 @@ impl MidasR {
     /// # Panics
     ///
     /// If `time < self.current_time()`
-    pub fn insert(&mut self, (source, dest, time): (Int, Int, Int)) -> Float {
+    pub fn insert(&mut self, (source, dest, time): (S, D, Int)) -> Float {
         assert!(self.current_time <= time);
 
+        let source = reduce_id(&source);
+        let dest = reduce_id(&dest);
+
+        self.advance_time(time);
+        self.insert_hashed(source, dest)
+    }
+
+    pub fn query(&self, source: S, dest: D) -> Float {
+        self.query_hashed(reduce_id(&source), reduce_id(&dest))
+    }
+
+    /// Decays the running counts if `time` is ahead of `self.current_time()`.
+    ///
+    /// This deviation from the original C++ implementation is
+    /// mentioned at
+    /// https://github.com/bhatiasiddharth/MIDAS/issues/7#issuecomment-597185695
+    fn advance_time(&mut self, time: Int) {
         if time > self.current_time {
-            // This deviation from the original C++ implementation is
-            // mentioned at
-            // https://github.com/bhatiasiddharth/MIDAS/issues/7#issuecomment-597185695
             let time_delta = time - self.current_time;
             let total_decay = self.alpha.powi(time_delta as _);
             self.current_count.lower(total_decay);
@@ -299,7 +434,12 @@ impl MidasR {
 
             self.current_time = time;
         }
+    }
 
+    /// Updates every CMS row for an already-hashed edge and returns its
+    /// score, without touching `current_time`. Shared by `insert` and
+    /// `insert_batch`.
+    fn insert_hashed(&mut self, source: Int, dest: Int) -> Float {
         self.current_count.insert(source, dest, 1.);
         self.total_count.insert(source, dest, 1.);
 
@@ -308,10 +448,51 @@ impl MidasR {
         self.source_total.insert(source, 1.);
         self.dest_total.insert(dest, 1.);
 
-        self.query(source, dest)
+        self.query_hashed(source, dest)
     }
 
-    pub fn query(&self, source: Int, dest: Int) -> Float {
+    /// Columnar batch ingestion: scores a run of `(sources[i], dests[i],
+    /// times[i])` edges, advancing/decaying the internal state once per
+    /// distinct timestamp rather than once per edge. `times` must be
+    /// non-decreasing, as with repeated calls to `insert`.
+    ///
+    /// Produces scores bit-identical to calling `insert` once per edge
+    /// in order.
+    ///
+    /// # Panics
+    ///
+    /// If `sources`, `dests`, and `times` have different lengths, or if
+    /// `times` ever decreases.
+    pub fn insert_batch(&mut self, sources: &[S], dests: &[D], times: &[Int]) -> Vec<Float> {
+        assert_eq!(sources.len(), times.len());
+        assert_eq!(dests.len(), times.len());
+
+        let mut scores = Vec::with_capacity(times.len());
+        let mut start = 0;
+
+        while start < times.len() {
+            let time = times[start];
+            let mut end = start + 1;
+            while end < times.len() && times[end] == time {
+                end += 1;
+            }
+
+            assert!(self.current_time <= time);
+            self.advance_time(time);
+
+            for i in start..end {
+                let source = reduce_id(&sources[i]);
+                let dest = reduce_id(&dests[i]);
+                scores.push(self.insert_hashed(source, dest));
+            }
+
+            start = end;
+        }
+
+        scores
+    }
+
+    fn query_hashed(&self, source: Int, dest: Int) -> Float {
         let current_score = counts_to_anom(
             self.total_count.count(source, dest),
             self.current_count.count(source, dest),
@@ -345,13 +526,40 @@ impl MidasR {
     /// Subsequent iterator will panic if ever passed a thruple where
     /// the third element (the time) decreases from its predecessor.
     pub fn iterate(
-        data: impl Iterator<Item = (Int, Int, Int)>,
+        data: impl Iterator<Item = (S, D, Int)>,
         params: MidasRParams,
     ) -> impl Iterator<Item = Float> {
         let mut midas = Self::new(params);
 
         data.map(move |datum| midas.insert(datum))
     }
+
+    /// Serializes the full detector state, including the realized hash
+    /// coefficients for every row, so `load` can resume without
+    /// re-seeding or replaying history.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn save(&self) -> serde_json::Result<String>
+    where
+        Self: serde::Serialize,
+    {
+        serde_json::to_string(self)
+    }
+
+    /// Restores a detector previously serialized with `save`. The
+    /// restored detector produces byte-identical scores to the one it
+    /// was saved from, since the hash coefficients are carried over
+    /// directly rather than re-derived from a seed.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn load(data: &str) -> serde_json::Result<Self>
+    where
+        Self: serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(data)
+    }
 }
 
 pub struct MidasParams {
@@ -374,6 +582,7 @@ impl Default for MidasParams {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Midas {
     current_time: Int,
     current_count: EdgeHash,
@@ -448,7 +657,179 @@ impl Midas {
     }
 }
 
-pub trait MidasIterator<'a>: 'a + Sized + Iterator<Item = (Int, Int, Int)> {
+pub struct MidasFParams {
+    /// Number of rows of buckets to use for internal Count-Min Sketches
+    pub rows: Int,
+    /// Number of buckets in each rows to use for internal Count-Min Sketches
+    pub buckets: Int,
+    /// Value used internally in determining bucket placement. Might be
+    /// made private in future version.
+    pub m_value: Int,
+    /// Factor used to to decay current values when our inputs signal
+    /// that time has ticked ahead.
+    pub alpha: Float,
+    /// Anomaly score above which a bucket's current count is treated as
+    /// an ongoing attack and only partially folded into the baseline
+    /// total, rather than fully merged.
+    pub threshold: Float,
+}
+
+impl Default for MidasFParams {
+    fn default() -> Self {
+        Self {
+            rows: default::NUM_ROWS,
+            buckets: default::NUM_BUCKETS,
+            m_value: default::M_VALUE,
+            alpha: default::ALPHA,
+            threshold: default::THRESHOLD,
+        }
+    }
+}
+
+/// The filtering variant of MIDAS-R. Unlike `MidasR`, which unconditionally
+/// folds every edge into its running total, `MidasF` folds its current
+/// counts into the total bucket by bucket on each time tick, scaling the
+/// contribution of any bucket whose score is at or above `threshold` down
+/// by `threshold / score`, so a sustained anomalous burst cannot corrupt
+/// the baseline mean that is used to detect it.
+pub struct MidasF {
+    current_time: Int,
+    alpha: Float,
+    threshold: Float,
+
+    current_count: EdgeHash,
+    total_count: EdgeHash,
+
+    source_score: NodeHash,
+    dest_score: NodeHash,
+    source_total: NodeHash,
+    dest_total: NodeHash,
+}
+
+impl MidasF {
+    pub fn new(
+        MidasFParams {
+            rows,
+            buckets,
+            m_value,
+            alpha,
+            threshold,
+        }: MidasFParams,
+    ) -> Self {
+        let dumb_seed = 2423;
+
+        Self {
+            current_time: 0,
+            alpha,
+            threshold,
+
+            // `current_count` and `total_count` are merged bucket-by-bucket
+            // (by position, not by re-hashing) in `merge_from`, so they
+            // must share the same per-row hash coefficients - built from
+            // the same seed - or a merged bucket ends up at a different
+            // index than `count` looks it up from.
+            current_count: EdgeHash::new(rows, buckets, m_value, dumb_seed + 1),
+            total_count: EdgeHash::new(rows, buckets, m_value, dumb_seed + 1),
+
+            source_score: NodeHash::new(rows, buckets, dumb_seed + 3),
+            dest_score: NodeHash::new(rows, buckets, dumb_seed + 4),
+            source_total: NodeHash::new(rows, buckets, dumb_seed + 5),
+            dest_total: NodeHash::new(rows, buckets, dumb_seed + 6),
+        }
+    }
+
+    pub fn current_time(&self) -> Int {
+        self.current_time
+    }
+
+    /// Factor used to to decay current values when our inputs signal
+    /// that time has ticked ahead.
+    pub fn alpha(&self) -> Float {
+        self.alpha
+    }
+
+    /// Anomaly score above which a bucket's current count is only
+    /// partially folded into the running total.
+    pub fn threshold(&self) -> Float {
+        self.threshold
+    }
+
+    /// # Panics
+    ///
+    /// If `time < self.current_time()`
+    pub fn insert(&mut self, (source, dest, time): (Int, Int, Int)) -> Float {
+        assert!(self.current_time <= time);
+
+        if time > self.current_time {
+            self.total_count
+                .merge_from(&self.current_count, self.current_time, self.threshold);
+
+            // This deviation from the original C++ implementation is
+            // mentioned at
+            // https://github.com/bhatiasiddharth/MIDAS/issues/7#issuecomment-597185695
+            let time_delta = time - self.current_time;
+            let total_decay = self.alpha.powi(time_delta as _);
+            self.current_count.lower(total_decay);
+            self.source_score.lower(total_decay);
+            self.dest_score.lower(total_decay);
+
+            self.current_time = time;
+        }
+
+        self.current_count.insert(source, dest, 1.);
+
+        self.source_score.insert(source, 1.);
+        self.dest_score.insert(dest, 1.);
+        self.source_total.insert(source, 1.);
+        self.dest_total.insert(dest, 1.);
+
+        self.query(source, dest)
+    }
+
+    pub fn query(&self, source: Int, dest: Int) -> Float {
+        let current_score = counts_to_anom(
+            self.total_count.count(source, dest),
+            self.current_count.count(source, dest),
+            self.current_time,
+        );
+        let current_score_source = counts_to_anom(
+            self.source_total.count(source),
+            self.source_score.count(source),
+            self.current_time,
+        );
+        let current_score_dest = counts_to_anom(
+            self.dest_total.count(dest),
+            self.dest_score.count(dest),
+            self.current_time,
+        );
+
+        float_max(
+            float_max(current_score_source, current_score_dest),
+            current_score,
+        )
+        .ln_1p()
+    }
+
+    /// Takes an iterator of `(source, dest, time)` thruples and returns
+    /// an iterator of corresponding scores.
+    ///
+    /// For a more ergonomic version, see `MidasIterator::midas_f`.
+    ///
+    /// # Panics
+    ///
+    /// Subsequent iterator will panic if ever passed a thruple where
+    /// the third element (the time) decreases from its predecessor.
+    pub fn iterate(
+        data: impl Iterator<Item = (Int, Int, Int)>,
+        params: MidasFParams,
+    ) -> impl Iterator<Item = Float> {
+        let mut midas = Self::new(params);
+
+        data.map(move |datum| midas.insert(datum))
+    }
+}
+
+pub trait MidasIterator<'a>: 'a + Sized {
     /// Takes an iterator of `(source, dest, time)` thruples and returns
     /// an iterator of corresponding scores.
     ///
@@ -458,12 +839,15 @@ pub trait MidasIterator<'a>: 'a + Sized + Iterator<Item = (Int, Int, Int)> {
     ///
     /// Subsequent iterator will panic if ever passed a thruple where
     /// the third element (the time) decreases from its predecessor.
-    fn midas(self, params: MidasParams) -> Box<dyn 'a + Iterator<Item = Float>> {
+    fn midas(self, params: MidasParams) -> Box<dyn 'a + Iterator<Item = Float>>
+    where
+        Self: Iterator<Item = (Int, Int, Int)>,
+    {
         Box::new(Midas::iterate(self, params))
     }
 
     fn thing() {
-        let iter = vec![(1, 1, 1), (1, 2, 1), (1, 1, 3), (1, 2, 4)]
+        let iter = vec![(1u64, 1u64, 1), (1, 2, 1), (1, 1, 3), (1, 2, 4)]
             .into_iter()
             .midas_r(Default::default());
 
@@ -473,15 +857,17 @@ pub trait MidasIterator<'a>: 'a + Sized + Iterator<Item = (Int, Int, Int)> {
     }
 
     /// Takes an iterator of `(source, dest, time)` thruples and returns
-    /// an iterator of corresponding scores.
+    /// an iterator of corresponding scores. `S`/`D` are threaded through
+    /// to [`MidasRGeneric::iterate`], so this works for any `S`/`D` that
+    /// `MidasRGeneric` accepts, not just the `Int`-only [`MidasR`] alias.
     ///
-    /// For a less ergonomic version, see `MidasR::iterate`.
+    /// For a less ergonomic version, see `MidasRGeneric::iterate`.
     ///
     /// ```rust
     /// # fn main() {
     /// use midas_rs::MidasIterator;
     ///
-    /// let iter = vec![(1, 1, 1), (1, 2, 1), (1, 1, 3), (1, 2, 4)]
+    /// let iter = vec![(1u64, 1u64, 1), (1, 2, 1), (1, 1, 3), (1, 2, 4)]
     ///     .into_iter()
     ///     .midas_r(Default::default());
     ///
@@ -495,9 +881,341 @@ pub trait MidasIterator<'a>: 'a + Sized + Iterator<Item = (Int, Int, Int)> {
     ///
     /// Subsequent iterator will panic if ever passed a thruple where
     /// the third element (the time) decreases from its predecessor.
-    fn midas_r(self, params: MidasRParams) -> Box<dyn 'a + Iterator<Item = Float>> {
-        Box::new(MidasR::iterate(self, params))
+    fn midas_r<S, D>(self, params: MidasRParams) -> Box<dyn 'a + Iterator<Item = Float>>
+    where
+        Self: Iterator<Item = (S, D, Int)>,
+        S: Hash + 'static,
+        D: Hash + 'static,
+    {
+        Box::new(MidasRGeneric::iterate(self, params))
+    }
+
+    /// Takes an iterator of `(source, dest, time)` thruples and returns
+    /// an iterator of corresponding scores.
+    ///
+    /// For a less ergonomic version, see `MidasF::iterate`.
+    ///
+    /// # Panics
+    ///
+    /// Subsequent iterator will panic if ever passed a thruple where
+    /// the third element (the time) decreases from its predecessor.
+    fn midas_f(self, params: MidasFParams) -> Box<dyn 'a + Iterator<Item = Float>>
+    where
+        Self: Iterator<Item = (Int, Int, Int)>,
+    {
+        Box::new(MidasF::iterate(self, params))
+    }
+}
+
+/// A detector that can score an edge thruple, implemented by `Midas`,
+/// `MidasR`, and `MidasF`. Lets `TopK` wrap any of them interchangeably.
+pub trait MidasDetector {
+    fn insert(&mut self, edge: (Int, Int, Int)) -> Float;
+}
+
+impl MidasDetector for Midas {
+    fn insert(&mut self, edge: (Int, Int, Int)) -> Float {
+        Midas::insert(self, edge)
+    }
+}
+
+impl MidasDetector for MidasR {
+    fn insert(&mut self, edge: (Int, Int, Int)) -> Float {
+        MidasR::insert(self, edge)
+    }
+}
+
+impl MidasDetector for MidasF {
+    fn insert(&mut self, edge: (Int, Int, Int)) -> Float {
+        MidasF::insert(self, edge)
+    }
+}
+
+/// A `Float` score paired with the edge it came from, ordered solely by
+/// score. `Float::partial_cmp` only returns `None` for NaN, which we
+/// treat as equal so this can never panic inside a `BinaryHeap`.
+#[derive(Clone, Copy)]
+struct ScoredEdge {
+    source: Int,
+    dest: Int,
+    time: Int,
+    score: Float,
+}
+
+impl PartialEq for ScoredEdge {
+    // Delegates to `cmp` (rather than deriving from the fields, which
+    // would compare `score == score` and be `false` for NaN) so this
+    // agrees with `Ord`/`PartialOrd` and the `Eq` impl below is honest.
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScoredEdge {}
+
+impl PartialOrd for ScoredEdge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredEdge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.partial_cmp(&other.score).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Wraps a `MidasDetector` and keeps a fixed-capacity min-heap of the
+/// highest-scoring edges seen so far, so callers don't have to buffer
+/// the whole stream to find the most anomalous edges.
+pub struct TopK<D> {
+    detector: D,
+    capacity: usize,
+    heap: BinaryHeap<Reverse<ScoredEdge>>,
+}
+
+impl<D: MidasDetector> TopK<D> {
+    pub fn new(detector: D, capacity: usize) -> Self {
+        Self {
+            detector,
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
+    /// Scores the edge via the wrapped detector and, if it's among the
+    /// `capacity` highest scores seen so far, records it.
+    pub fn insert(&mut self, edge @ (source, dest, time): (Int, Int, Int)) -> Float {
+        let score = self.detector.insert(edge);
+        let entry = ScoredEdge {
+            source,
+            dest,
+            time,
+            score,
+        };
+
+        if self.heap.len() < self.capacity {
+            self.heap.push(Reverse(entry));
+        } else if let Some(Reverse(min)) = self.heap.peek() {
+            if entry > *min {
+                self.heap.pop();
+                self.heap.push(Reverse(entry));
+            }
+        }
+
+        score
+    }
+
+    /// Returns the highest-scoring `(source, dest, time, score)` records
+    /// seen so far, sorted descending by score.
+    pub fn top_k(&self) -> Vec<(Int, Int, Int, Float)> {
+        let mut entries: Vec<ScoredEdge> = self.heap.iter().map(|Reverse(entry)| *entry).collect();
+        entries.sort_by(|a, b| b.cmp(a));
+
+        entries
+            .into_iter()
+            .map(|entry| (entry.source, entry.dest, entry.time, entry.score))
+            .collect()
     }
 }
 
-impl<'a, T> MidasIterator<'a> for T where T: 'a + Iterator<Item = (Int, Int, Int)> + Sized {}
+impl<'a, T> MidasIterator<'a> for T where T: 'a + Sized {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn midas_f_survives_multiple_time_ticks() {
+        // Regression test for a bug where merging an untouched bucket
+        // (zero baseline, zero current count) divided zero by zero in
+        // `counts_to_anom`, poisoning the baseline with NaN (and, on the
+        // very first tick, underflowing the unsigned `current_time - 1`
+        // in a debug build).
+        let mut midas = MidasF::new(MidasFParams::default());
+
+        let scores: Vec<Float> = vec![
+            (1, 1, 1),
+            (1, 2, 1),
+            (2, 1, 1),
+            (1, 1, 2),
+            (1, 2, 3),
+            (1, 2, 3),
+            (3, 3, 4),
+        ]
+        .into_iter()
+        .map(|edge| midas.insert(edge))
+        .collect();
+
+        assert!(scores.iter().all(|score| score.is_finite()));
+    }
+
+    #[test]
+    fn midas_r_scores_are_pinned_for_int_ids() {
+        // Regression test for a bug where `MidasRGeneric::insert`/`query`
+        // ran every id - including an already-`Int` id - through
+        // `hash_id` before it reached the Count-Min Sketch. That silently
+        // changed `MidasR`'s (the `MidasRGeneric<Int, Int>` alias) score
+        // sequence relative to the original, non-generic implementation,
+        // especially under a small, collision-prone bucket count. Pin
+        // exact values (rather than just a round-trip self-consistency
+        // check) so this catches a regression even if `reduce_id`'s
+        // `Int` bypass is lost but the round trip still happens to be
+        // internally consistent.
+        let params = MidasRParams {
+            rows: 2,
+            buckets: 11,
+            m_value: 2_000_003,
+            alpha: 0.6,
+        };
+        let mut midas = MidasR::new(params);
+
+        let scores: Vec<Float> = vec![
+            (1, 1, 1),
+            (1, 2, 1),
+            (2, 1, 1),
+            (1, 1, 2),
+            (1, 2, 3),
+            (1, 2, 3),
+            (3, 3, 4),
+            (1, 1, 4),
+        ]
+        .into_iter()
+        .map(|edge| midas.insert(edge))
+        .collect();
+
+        let expected = [
+            0.0000000000,
+            0.0000000000,
+            0.0000000000,
+            0.5423242908,
+            0.7396490142,
+            1.3282414297,
+            1.3862943611,
+            1.0914948717,
+        ];
+
+        for (score, expected) in scores.iter().zip(expected.iter()) {
+            assert!(
+                (score - expected).abs() < 1e-9,
+                "got {score}, expected {expected}"
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn midas_r_save_load_round_trip_continues_identically() {
+        let mut midas = MidasR::new(MidasRParams::default());
+
+        for edge in [(1, 1, 1), (1, 2, 1), (2, 1, 2)] {
+            midas.insert(edge);
+        }
+
+        let saved = midas.save().unwrap();
+        let mut restored = MidasR::load(&saved).unwrap();
+
+        // The restored detector carries over the realized hash
+        // coefficients rather than re-seeding, so it must continue
+        // producing the exact same scores as the original for the same
+        // subsequent input.
+        for edge in [(1, 1, 2), (3, 3, 3), (1, 2, 4)] {
+            assert_eq!(midas.insert(edge), restored.insert(edge));
+        }
+
+        assert_eq!(midas.save().unwrap(), restored.save().unwrap());
+    }
+
+    // A detector that just plays back a fixed score per edge, so a test
+    // can pin exactly which edges `TopK` should keep.
+    struct FixedScores(std::vec::IntoIter<Float>);
+
+    impl MidasDetector for FixedScores {
+        fn insert(&mut self, _edge: (Int, Int, Int)) -> Float {
+            self.0.next().expect("test only inserts as many edges as scores")
+        }
+    }
+
+    #[test]
+    fn top_k_keeps_only_the_highest_scores_sorted_descending() {
+        let scores = vec![1.0, 5.0, 4.0, 3.0, 9.0, 2.0];
+        let mut top_k = TopK::new(FixedScores(scores.clone().into_iter()), 3);
+
+        for i in 0..scores.len() as Int {
+            top_k.insert((i, i, i));
+        }
+
+        let top = top_k.top_k();
+        let kept_scores: Vec<Float> = top.iter().map(|&(.., score)| score).collect();
+
+        assert_eq!(kept_scores, vec![9.0, 5.0, 4.0]);
+        assert!(kept_scores.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn top_k_does_not_panic_on_nan_scores() {
+        let mut top_k = TopK::new(FixedScores(vec![1.0, Float::NAN, 2.0].into_iter()), 2);
+
+        for i in 0..3 {
+            top_k.insert((i, i, i));
+        }
+
+        assert_eq!(top_k.top_k().len(), 2);
+    }
+
+    #[test]
+    fn insert_batch_matches_per_tuple_insert_bit_for_bit() {
+        let edges: Vec<(Int, Int, Int)> = vec![
+            (1, 1, 1),
+            (1, 2, 1),
+            (2, 1, 1),
+            (1, 1, 2),
+            (1, 2, 3),
+            (1, 2, 3),
+            (3, 3, 4),
+            (3, 3, 4),
+            (1, 1, 4),
+        ];
+
+        let mut by_tuple = MidasR::new(MidasRParams::default());
+        let tuple_scores: Vec<Float> = edges.iter().map(|&edge| by_tuple.insert(edge)).collect();
+
+        let sources: Vec<Int> = edges.iter().map(|&(s, ..)| s).collect();
+        let dests: Vec<Int> = edges.iter().map(|&(_, d, _)| d).collect();
+        let times: Vec<Int> = edges.iter().map(|&(.., t)| t).collect();
+
+        let mut by_batch = MidasR::new(MidasRParams::default());
+        let batch_scores = by_batch.insert_batch(&sources, &dests, &times);
+
+        assert_eq!(tuple_scores, batch_scores);
+    }
+
+    #[test]
+    fn midas_iterator_midas_r_threads_non_int_ids() {
+        // Regression test for a bug where `MidasIterator::midas_r` only
+        // accepted `Iterator<Item = (Int, Int, Int)>`, making it
+        // unusable with the non-`Int` id types `MidasRGeneric` supports.
+        let scores: Vec<Float> = vec![("a", "a", 1), ("a", "b", 1), ("b", "a", 2)]
+            .into_iter()
+            .midas_r(MidasRParams::default())
+            .collect();
+
+        assert_eq!(scores.len(), 3);
+        assert!(scores.iter().all(|score| score.is_finite()));
+    }
+
+    #[test]
+    fn scored_edge_eq_is_reflexive_for_nan_scores() {
+        // Regression test: a derived `PartialEq` compares `score == score`
+        // field-wise, which is `false` for NaN and would violate `Eq`'s
+        // reflexivity law even though `Ord::cmp` treats NaN as equal.
+        let edge = ScoredEdge {
+            source: 1,
+            dest: 2,
+            time: 3,
+            score: Float::NAN,
+        };
+
+        assert!(edge == edge);
+    }
+}